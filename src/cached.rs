@@ -0,0 +1,36 @@
+//! A request-scoped memoizing extractor.
+//!
+//! Axum re-runs an extractor every time it's asked for, even if several
+//! handlers or middleware in the same request want the same derived value.
+//! [`Cached<T>`] runs `T`'s extraction once per request and stashes the
+//! result in the request extensions so later extractions just clone it.
+
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+
+struct CachedEntry<T>(T);
+
+/// Wraps an extractor `T`, memoizing its result for the lifetime of the
+/// request. The first extraction runs `T::from_request_parts` as normal and
+/// inserts the value into the request extensions; every later extraction of
+/// `Cached<T>` within the same request just clones it out.
+pub struct Cached<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Cached<T>
+where
+    T: FromRequestParts<S> + Clone + Send + Sync + 'static,
+    S: Send + Sync,
+{
+    type Rejection = T::Rejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        if let Some(CachedEntry(value)) = parts.extensions.get::<CachedEntry<T>>() {
+            return Ok(Cached(value.clone()));
+        }
+
+        let value = T::from_request_parts(parts, state).await?;
+        parts.extensions.insert(CachedEntry(value.clone()));
+
+        Ok(Cached(value))
+    }
+}
@@ -0,0 +1,238 @@
+//! Route guards: composable predicates that pick which of several handlers
+//! registered on the same route should run, instead of branching inside the
+//! handler body.
+
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    http::{HeaderName, HeaderValue, Method as HttpMethod, Uri},
+    response::{IntoResponse, Response},
+};
+use hyper::{Body, Request, StatusCode};
+use tower::{util::ServiceExt, Service};
+
+/// The parts of a request a [`Guard`] is allowed to inspect.
+pub struct RequestParts<'a> {
+    pub headers: &'a axum::http::HeaderMap,
+    pub uri: &'a Uri,
+    pub method: &'a HttpMethod,
+}
+
+impl<'a> RequestParts<'a> {
+    pub fn from_request<B>(req: &'a Request<B>) -> Self {
+        Self {
+            headers: req.headers(),
+            uri: req.uri(),
+            method: req.method(),
+        }
+    }
+}
+
+/// A predicate over a request, used to decide whether a guarded route
+/// should handle it.
+pub trait Guard: Send + Sync + 'static {
+    fn check(&self, req: &RequestParts<'_>) -> bool;
+}
+
+/// Matches every request; useful as the final "otherwise" arm of a guarded
+/// route.
+pub struct Always;
+
+impl Guard for Always {
+    fn check(&self, _req: &RequestParts<'_>) -> bool {
+        true
+    }
+}
+
+/// Matches when every inner guard matches.
+pub struct All(pub Vec<Box<dyn Guard>>);
+
+impl Guard for All {
+    fn check(&self, req: &RequestParts<'_>) -> bool {
+        self.0.iter().all(|guard| guard.check(req))
+    }
+}
+
+/// Matches when any inner guard matches.
+pub struct Any(pub Vec<Box<dyn Guard>>);
+
+impl Guard for Any {
+    fn check(&self, req: &RequestParts<'_>) -> bool {
+        self.0.iter().any(|guard| guard.check(req))
+    }
+}
+
+/// Matches when the inner guard does not.
+pub struct Not(pub Box<dyn Guard>);
+
+impl Guard for Not {
+    fn check(&self, req: &RequestParts<'_>) -> bool {
+        !self.0.check(req)
+    }
+}
+
+/// Matches when `name` is present, optionally requiring an exact value.
+pub struct Header {
+    name: HeaderName,
+    value: Option<HeaderValue>,
+}
+
+impl Header {
+    pub fn present(name: HeaderName) -> Self {
+        Self { name, value: None }
+    }
+
+    pub fn exact(name: HeaderName, value: HeaderValue) -> Self {
+        Self {
+            name,
+            value: Some(value),
+        }
+    }
+}
+
+impl Guard for Header {
+    fn check(&self, req: &RequestParts<'_>) -> bool {
+        match req.headers.get(&self.name) {
+            Some(actual) => self.value.as_ref().is_none_or(|expected| actual == expected),
+            None => false,
+        }
+    }
+}
+
+/// Matches when the query string contains `name`, regardless of its value.
+pub struct QueryParam {
+    name: String,
+}
+
+impl QueryParam {
+    pub fn present(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+impl Guard for QueryParam {
+    fn check(&self, req: &RequestParts<'_>) -> bool {
+        req.uri
+            .query()
+            .map(|query| form_urlencoded::parse(query.as_bytes()).any(|(key, _)| key == self.name))
+            .unwrap_or(false)
+    }
+}
+
+/// Matches when the request's HTTP method equals `self.0`. Axum's own
+/// route table (`.get()`/`.post()`/etc.) already dispatches by method for
+/// distinct routes, so this guard only earns its keep *inside* a single
+/// [`GuardedRouterBuilder`] dispatch, where one route wants to fall through
+/// to different handlers by method as well as by header or content-type.
+pub struct Method(pub HttpMethod);
+
+impl Guard for Method {
+    fn check(&self, req: &RequestParts<'_>) -> bool {
+        *req.method == self.0
+    }
+}
+
+/// Matches when `Content-Type`'s media type (ignoring any `;charset=...`
+/// parameters) is exactly the given MIME type.
+pub struct ContentType(pub &'static str);
+
+impl Guard for ContentType {
+    fn check(&self, req: &RequestParts<'_>) -> bool {
+        req.headers
+            .get(axum::http::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|media_type| media_type.trim() == self.0)
+            .unwrap_or(false)
+    }
+}
+
+/// A type-erased handler, dispatched via `Service::oneshot` on a fresh
+/// clone each call so the stored value itself only needs to be `Send +
+/// Sync`, not `Sync` *and* interior-mutably callable through `&self`.
+type BoxedHandler =
+    Arc<dyn Fn(Request<Body>) -> Pin<Box<dyn Future<Output = Response> + Send>> + Send + Sync>;
+
+fn box_handler<T>(service: T) -> BoxedHandler
+where
+    T: Service<Request<Body>, Response = Response, Error = Infallible> + Clone + Send + Sync + 'static,
+    T::Future: Send + 'static,
+{
+    Arc::new(move |req| {
+        let service = service.clone();
+        Box::pin(async move { service.oneshot(req).await.unwrap() })
+    })
+}
+
+/// Builds a [`GuardedRouter`] out of `(Guard, Service)` pairs, tried in
+/// registration order.
+pub struct GuardedRouterBuilder {
+    routes: Vec<(Box<dyn Guard>, BoxedHandler)>,
+}
+
+impl GuardedRouterBuilder {
+    pub fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    pub fn route<G, T>(mut self, guard: G, service: T) -> Self
+    where
+        G: Guard,
+        T: Service<Request<Body>, Response = Response, Error = Infallible> + Clone + Send + Sync + 'static,
+        T::Future: Send + 'static,
+    {
+        self.routes.push((Box::new(guard), box_handler(service)));
+        self
+    }
+
+    pub fn build(self) -> GuardedRouter {
+        GuardedRouter {
+            routes: Arc::new(self.routes),
+        }
+    }
+}
+
+impl Default for GuardedRouterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Dispatches a request to the first registered handler whose guard
+/// matches, falling through to `404 Not Found` if none do.
+#[derive(Clone)]
+pub struct GuardedRouter {
+    routes: Arc<Vec<(Box<dyn Guard>, BoxedHandler)>>,
+}
+
+impl Service<Request<Body>> for GuardedRouter {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let parts = RequestParts::from_request(&req);
+        let matched = self
+            .routes
+            .iter()
+            .find(|(guard, _)| guard.check(&parts))
+            .map(|(_, handler)| Arc::clone(handler));
+
+        match matched {
+            Some(handler) => Box::pin(async move { Ok(handler(req).await) }),
+            None => Box::pin(async move {
+                Ok((StatusCode::NOT_FOUND, "No guard matched this request").into_response())
+            }),
+        }
+    }
+}
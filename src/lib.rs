@@ -1,8 +1,4 @@
-use std::{
-    collections::HashMap,
-    sync::{Arc, PoisonError, RwLock},
-    time::Duration,
-};
+use std::{sync::Arc, time::Duration};
 
 use axum::{
     body::Bytes,
@@ -16,18 +12,110 @@ use axum::{
 
 use hyper::{Body, Request, StatusCode};
 use serde::Deserialize;
-use tower::{timeout::TimeoutLayer, Layer, Service, ServiceBuilder};
-use tower_http::auth::RequireAuthorizationLayer;
+use tower::{service_fn, timeout::TimeoutLayer, Layer, Service, ServiceBuilder};
+use tower_http::{
+    auth::RequireAuthorizationLayer,
+    compression::{predicate::SizeAbove, CompressionLayer},
+    services::ServeDir,
+};
+use tracing::Instrument;
+
+mod cached;
+pub mod guard;
+mod store;
+
+pub use cached::Cached;
+pub use guard::{Guard, GuardedRouterBuilder};
+pub use store::{MemoryStore, PostgresStore, Store, StoreError};
+
+/// Values smaller than this are left uncompressed; the framing overhead of
+/// gzip/brotli isn't worth it for tiny bodies.
+const COMPRESSION_MIN_SIZE: u16 = 256;
+
+/// Negotiates gzip/deflate/brotli from `Accept-Encoding` and only compresses
+/// responses at or above [`COMPRESSION_MIN_SIZE`] bytes.
+fn compression_layer() -> CompressionLayer<SizeAbove> {
+    CompressionLayer::new()
+        .gzip(true)
+        .deflate(true)
+        .br(true)
+        .compress_when(SizeAbove::new(COMPRESSION_MIN_SIZE))
+}
+
+/// Custom type for a shared state, generic over the [`Store`] backing it.
+pub type SharedState<S = MemoryStore> = Arc<AppState<S>>;
+
+/// Default for [`AppState::sweep_interval`] when built via `AppState::new`
+/// or `AppState::default`.
+const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Default for [`AppState::static_dir`] when built via `AppState::new` or
+/// `AppState::default`.
+const DEFAULT_STATIC_DIR: &str = "static";
+
+pub struct AppState<S = MemoryStore> {
+    store: S,
+    /// How often the background sweeper scans the store for expired keys;
+    /// see [`AppState::with_sweep_interval`] to override it.
+    sweep_interval: Duration,
+    /// Root directory served under `/static`; see [`AppState::with_static_dir`]
+    /// to override it.
+    static_dir: String,
+}
+
+impl<S> AppState<S> {
+    pub fn new(store: S) -> Self {
+        Self::with_sweep_interval(store, DEFAULT_SWEEP_INTERVAL)
+    }
 
-/// Custom type for a shared state
-pub type SharedState = Arc<RwLock<AppState>>;
-#[derive(Default)]
-pub struct AppState {
-    db: HashMap<String, Bytes>,
+    pub fn with_sweep_interval(store: S, sweep_interval: Duration) -> Self {
+        Self {
+            store,
+            sweep_interval,
+            static_dir: DEFAULT_STATIC_DIR.to_owned(),
+        }
+    }
+
+    pub fn with_static_dir(mut self, static_dir: impl Into<String>) -> Self {
+        self.static_dir = static_dir.into();
+        self
+    }
 }
 
-pub fn router(state: &SharedState) -> Router<SharedState> {
-    Router::with_state(Arc::clone(state))
+impl<S: Default> Default for AppState<S> {
+    fn default() -> Self {
+        Self::new(S::default())
+    }
+}
+
+async fn handle_not_found(_: Request<Body>) -> Result<axum::response::Response, std::io::Error> {
+    Ok((StatusCode::NOT_FOUND, "Not Found").into_response())
+}
+
+/// Periodically removes expired entries so keys that are never read again
+/// still get reclaimed, rather than relying solely on lazy eviction in
+/// `handler_kv_get`. The scan cadence comes from `state.sweep_interval`, so
+/// callers can tune it via `AppState::with_sweep_interval`.
+fn spawn_expiry_sweeper<S: Store>(state: SharedState<S>) {
+    let interval = state.sweep_interval;
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            state.store.sweep_expired().await;
+        }
+    });
+}
+
+pub fn router<S: Store>(state: &SharedState<S>) -> Router {
+    spawn_expiry_sweeper(Arc::clone(state));
+
+    let static_files = ServiceBuilder::new()
+        .layer(HandleErrorLayer::<_, ()>::new(handle_static_error))
+        .service(ServeDir::new(&state.static_dir).not_found_service(service_fn(handle_not_found)));
+
+    Router::new()
         .route("/", get(hello_axum))
         .route("/hello", get(handler_hello))
         .route(
@@ -35,56 +123,66 @@ pub fn router(state: &SharedState) -> Router<SharedState> {
             get(handler_kv_get).post_service(
                 ServiceBuilder::new()
                     .layer(DefaultBodyLimit::disable())
-                    .service(handler_kv_post.with_state(Arc::clone(state))),
+                    .service(
+                        GuardedRouterBuilder::new()
+                            .route(
+                                guard::ContentType("application/json"),
+                                handler_kv_post_json.with_state(Arc::clone(state)),
+                            )
+                            .route(guard::Always, handler_kv_post.with_state(Arc::clone(state)))
+                            .build(),
+                    ),
             ),
         )
-        .nest("/admin", admin_routes(state))
+        .nest("/admin", admin_routes::<S>())
+        .nest_service("/static", static_files)
         .layer(
             ServiceBuilder::new()
                 .layer(HandleErrorLayer::new(handle_error))
-                .layer(TimeoutLayer::new(Duration::from_secs(5))),
+                .layer(TimeoutLayer::new(Duration::from_secs(5)))
+                .layer(compression_layer()),
         )
-        .layer(LoggerMiddleware::new())
+        .layer(RequestTracingLayer::new())
+        .with_state(Arc::clone(state))
 }
 
-fn admin_routes(state: &SharedState) -> Router<SharedState> {
-    Router::with_state(Arc::clone(state))
+fn admin_routes<S: Store>() -> Router<SharedState<S>> {
+    Router::new()
         .route("/kv", delete(admin_handle_delete))
         .route("/kv/:key", delete(admin_handle_delete_key))
         .layer(RequireAuthorizationLayer::bearer("secret"))
 }
 
-async fn admin_handle_delete_key(
+/// Deletes `key`, which also clears any pending expiration since the TTL is
+/// stored alongside the value itself.
+async fn admin_handle_delete_key<S: Store>(
     Path(key): Path<String>,
-    State(state): State<SharedState>,
-) -> (StatusCode, &'static str) {
-    let mut state = match state.write() {
-        Ok(state) => state,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database corrupted"),
-    };
-
-    let _ = state.db.remove(&key);
+    State(state): State<SharedState<S>>,
+) -> Result<(StatusCode, &'static str), DbError> {
+    state.store.delete(&key).await?;
 
-    (StatusCode::OK, "Deleted entry")
+    Ok((StatusCode::OK, "Deleted entry"))
 }
 
-async fn admin_handle_delete(State(state): State<SharedState>) -> (StatusCode, &'static str) {
-    let mut state = match state.write() {
-        Ok(state) => state,
-        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, "Database corrupted"),
-    };
+async fn admin_handle_delete<S: Store>(
+    State(state): State<SharedState<S>>,
+) -> Result<(StatusCode, &'static str), DbError> {
+    state.store.clear().await?;
 
-    state.db.clear();
+    Ok((StatusCode::OK, "Deleted all entries"))
+}
 
-    (StatusCode::OK, "Deleted all entries")
+async fn handle_static_error(err: std::io::Error) -> (StatusCode, &'static str) {
+    tracing::error!(error = %err, "io error serving static asset");
+    (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
 }
 
 async fn handle_error(err: BoxError) -> (StatusCode, &'static str) {
     if err.is::<tower::timeout::error::Elapsed>() {
-        eprintln!("Request timed out: {}", err);
+        tracing::warn!(error = %err, "request timed out");
         return (StatusCode::REQUEST_TIMEOUT, "Request timed out");
     } else if err.is::<std::io::Error>() {
-        eprintln!("IO Error: {}", err);
+        tracing::error!(error = %err, "io error");
         return (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error");
     }
     (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
@@ -107,86 +205,144 @@ async fn hello_axum() -> &'static str {
     "<h1>Hello Axum</h1>"
 }
 
-async fn handler_kv_post(
+/// The authenticated identity for a request, derived from `x-user-id`.
+/// Cheap to extract, but a stand-in for the kind of derived value (parsed
+/// auth principal, deserialized body) that's worth extracting only once.
+#[derive(Clone)]
+struct Principal(String);
+
+#[async_trait::async_trait]
+impl<S> axum::extract::FromRequestParts<S> for Principal
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let name = parts
+            .headers
+            .get("x-user-id")
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("anonymous")
+            .to_owned();
+
+        Ok(Self(name))
+    }
+}
+
+#[derive(Deserialize)]
+struct TtlParams {
+    ttl: Option<u64>,
+}
+
+/// Resolves a requested TTL from the `?ttl=<seconds>` query param, falling
+/// back to the `X-TTL` header.
+fn resolve_ttl(query_ttl: Option<u64>, headers: &axum::http::HeaderMap) -> Option<Duration> {
+    query_ttl
+        .or_else(|| {
+            headers
+                .get("x-ttl")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+        })
+        .map(Duration::from_secs)
+}
+
+async fn handler_kv_post<S: Store>(
     Path(key): Path<String>,
-    State(state): State<SharedState>,
+    State(state): State<SharedState<S>>,
+    Cached(principal): Cached<Principal>,
+    Query(TtlParams { ttl }): Query<TtlParams>,
+    headers: axum::http::HeaderMap,
     bytes: Bytes,
-) -> Result<&'static str, (StatusCode, &'static str)> {
-    let mut state = match state.write() {
-        Ok(state) => state,
-        Err(_) => return Err((StatusCode::INTERNAL_SERVER_ERROR, "Database corrupted")),
-    };
+) -> Result<&'static str, DbError> {
+    let ttl = resolve_ttl(ttl, &headers);
+    tracing::debug!(user = %principal.0, %key, ?ttl, "writing key");
 
-    state.db.insert(key, bytes);
+    state.store.put(key, bytes, ttl).await?;
 
     Ok("Inserted key")
 }
 
-struct DbError(StatusCode, &'static str);
-
-impl<T> From<PoisonError<T>> for DbError {
-    fn from(_: PoisonError<T>) -> Self {
-        Self(StatusCode::INTERNAL_SERVER_ERROR, "Database corrupted")
+/// Guarded alongside [`handler_kv_post`] by `Content-Type`: this one runs
+/// when the body claims to be JSON, and rejects it if it isn't well-formed.
+async fn handler_kv_post_json<S: Store>(
+    Path(key): Path<String>,
+    State(state): State<SharedState<S>>,
+    Cached(principal): Cached<Principal>,
+    Query(TtlParams { ttl }): Query<TtlParams>,
+    headers: axum::http::HeaderMap,
+    bytes: Bytes,
+) -> Result<&'static str, DbError> {
+    if serde_json::from_slice::<serde_json::Value>(&bytes).is_err() {
+        return Err(DbError(StatusCode::BAD_REQUEST, "Invalid JSON body".to_owned()));
     }
-}
 
-impl IntoResponse for DbError {
-    fn into_response(self) -> axum::response::Response {
-        (self.0, self.1).into_response()
-    }
-}
+    let ttl = resolve_ttl(ttl, &headers);
+    tracing::debug!(user = %principal.0, %key, ?ttl, "writing json key");
 
-struct Point<T, U> {
-    x: T,
-    y: U,
-}
+    state.store.put(key, bytes, ttl).await?;
 
-impl<T> Point<T, T> {
-    fn new(x: T, y: T) -> Self {
-        Self { x, y }
-    }
+    Ok("Inserted key")
 }
 
-impl Point<i32, i32> {
-    fn sum(&self) -> i32 {
-        self.x + self.y
+struct DbError(StatusCode, String);
+
+impl From<StoreError> for DbError {
+    fn from(err: StoreError) -> Self {
+        tracing::error!(error = %err, "store error");
+        Self(StatusCode::INTERNAL_SERVER_ERROR, "Database corrupted".to_owned())
     }
 }
 
-fn _foo() {
-    let int_point = Point::new(1, 2);
-    int_point.sum();
-    let _str_point = Point::new("a", "b");
+impl IntoResponse for DbError {
+    fn into_response(self) -> axum::response::Response {
+        (self.0, self.1).into_response()
+    }
 }
 
-async fn handler_kv_get(
+async fn handler_kv_get<S: Store>(
     Path(key): Path<String>,
-    State(state): State<SharedState>,
+    State(state): State<SharedState<S>>,
+    Cached(principal): Cached<Principal>,
 ) -> Result<Bytes, DbError> {
-    match state.read()?.db.get(&key) {
-        Some(val) => Ok(val.clone()),
-        None => Err(DbError(StatusCode::NOT_FOUND, "Key not found")),
+    tracing::debug!(user = %principal.0, %key, "reading key");
+
+    match state.store.get(&key).await? {
+        Some(val) => Ok(val),
+        None => Err(DbError(StatusCode::NOT_FOUND, "Key not found".to_owned())),
     }
 }
 
+/// The header an upstream caller can set to correlate a request across
+/// services; when absent we mint one so every request still gets a span.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
 #[derive(Clone, Copy)]
-struct Logger<S> {
+struct RequestTracing<S> {
     inner: S,
 }
 
-impl<S> Logger<S> {
+impl<S> RequestTracing<S> {
     fn new(inner: S) -> Self {
         Self { inner }
     }
 }
 
-impl<IS> Service<Request<Body>> for Logger<IS>
+impl<IS> Service<Request<Body>> for RequestTracing<IS>
 where
-    IS: Service<Request<Body>>,
+    IS: Service<Request<Body>, Response = axum::response::Response>,
+    IS::Future: Send + 'static,
+    IS::Error: std::fmt::Display,
 {
     type Response = IS::Response;
     type Error = IS::Error;
-    type Future = IS::Future;
+    type Future = std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>> + Send>,
+    >;
 
     fn poll_ready(
         &mut self,
@@ -196,23 +352,53 @@ where
     }
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
-        println!("{} {}", req.method(), req.uri());
-        self.inner.call(req)
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        let span = tracing::info_span!(
+            "request",
+            method = %req.method(),
+            path = %req.uri().path(),
+            request_id = %request_id,
+        );
+
+        let start = std::time::Instant::now();
+        let fut = self.inner.call(req);
+
+        Box::pin(
+            async move {
+                let result = fut.await;
+                let elapsed_ms = start.elapsed().as_millis();
+                match &result {
+                    Ok(response) => {
+                        tracing::info!(status = %response.status(), elapsed_ms, "request completed")
+                    }
+                    Err(err) => tracing::error!(%err, elapsed_ms, "request failed"),
+                }
+                result
+            }
+            .instrument(span),
+        )
     }
 }
 
-struct LoggerMiddleware;
+#[derive(Clone)]
+struct RequestTracingLayer;
 
-impl LoggerMiddleware {
+impl RequestTracingLayer {
     fn new() -> Self {
         Self
     }
 }
 
-impl<S> Layer<S> for LoggerMiddleware {
-    type Service = Logger<S>;
+impl<S> Layer<S> for RequestTracingLayer {
+    type Service = RequestTracing<S>;
 
     fn layer(&self, inner: S) -> Self::Service {
-        Logger::new(inner)
+        RequestTracing::new(inner)
     }
 }
@@ -0,0 +1,176 @@
+//! Storage backends for the KV service.
+//!
+//! Handlers talk to state through the [`Store`] trait instead of reaching
+//! into a concrete map, so the same routes work whether the data lives in
+//! memory or in Postgres.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{PoisonError, RwLock},
+    time::{Duration, Instant},
+};
+
+use axum::body::Bytes;
+use bb8::Pool;
+use bb8_postgres::PostgresConnectionManager;
+use tokio_postgres::NoTls;
+
+/// A storage backend for the KV service.
+#[async_trait::async_trait]
+pub trait Store: Send + Sync + 'static {
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError>;
+
+    /// Stores `value` under `key`. `ttl` is a hint: backends without a
+    /// notion of expiry are free to ignore it.
+    async fn put(&self, key: String, value: Bytes, ttl: Option<Duration>) -> Result<(), StoreError>;
+    async fn delete(&self, key: &str) -> Result<(), StoreError>;
+    async fn clear(&self) -> Result<(), StoreError>;
+
+    /// Proactively removes entries whose TTL has elapsed. Backends that
+    /// don't support TTLs can leave this as a no-op; backends that do may
+    /// also evict lazily on `get`.
+    async fn sweep_expired(&self) {}
+}
+
+/// An opaque storage failure, carrying just enough detail to log and to map
+/// onto an HTTP response.
+#[derive(Debug)]
+pub struct StoreError(String);
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+impl<T> From<PoisonError<T>> for StoreError {
+    fn from(_: PoisonError<T>) -> Self {
+        Self("database lock poisoned".to_owned())
+    }
+}
+
+/// The original hand-rolled store: an `RwLock`-guarded `HashMap`, kept
+/// around as the default backend for tests and local development. Entries
+/// carry an optional expiry, evicted lazily on `get` and actively by
+/// [`MemoryStore::sweep_expired`].
+#[derive(Default)]
+pub struct MemoryStore {
+    db: RwLock<HashMap<String, (Bytes, Option<Instant>)>>,
+}
+
+impl MemoryStore {
+    fn is_expired(expires_at: Option<Instant>) -> bool {
+        expires_at.is_some_and(|at| at <= Instant::now())
+    }
+}
+
+#[async_trait::async_trait]
+impl Store for MemoryStore {
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError> {
+        let expired = match self.db.read()?.get(key) {
+            Some((_, expires_at)) => Self::is_expired(*expires_at),
+            None => return Ok(None),
+        };
+
+        if expired {
+            self.db.write()?.remove(key);
+            return Ok(None);
+        }
+
+        Ok(self.db.read()?.get(key).map(|(value, _)| value.clone()))
+    }
+
+    async fn put(&self, key: String, value: Bytes, ttl: Option<Duration>) -> Result<(), StoreError> {
+        let expires_at = ttl.map(|ttl| Instant::now() + ttl);
+        self.db.write()?.insert(key, (value, expires_at));
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError> {
+        self.db.write()?.remove(key);
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), StoreError> {
+        self.db.write()?.clear();
+        Ok(())
+    }
+
+    async fn sweep_expired(&self) {
+        if let Ok(mut db) = self.db.write() {
+            db.retain(|_, (_, expires_at)| !Self::is_expired(*expires_at));
+        }
+    }
+}
+
+/// A `Store` backed by a `bb8`-pooled Postgres connection, persisting
+/// entries to a `kv_store(key TEXT PRIMARY KEY, value BYTEA)` table.
+pub struct PostgresStore {
+    pool: Pool<PostgresConnectionManager<NoTls>>,
+}
+
+impl PostgresStore {
+    /// Connects using a `tokio_postgres` connection string and builds the
+    /// pool that backs every subsequent query.
+    pub async fn connect(config: &str) -> Result<Self, StoreError> {
+        let manager = PostgresConnectionManager::new_from_stringlike(config, NoTls)
+            .map_err(|err| StoreError(err.to_string()))?;
+        let pool = Pool::builder()
+            .build(manager)
+            .await
+            .map_err(|err| StoreError(err.to_string()))?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait::async_trait]
+impl Store for PostgresStore {
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError> {
+        let conn = self.pool.get().await.map_err(|err| StoreError(err.to_string()))?;
+        let row = conn
+            .query_opt("SELECT value FROM kv_store WHERE key = $1", &[&key])
+            .await
+            .map_err(|err| StoreError(err.to_string()))?;
+
+        Ok(row.map(|row| Bytes::from(row.get::<_, Vec<u8>>("value"))))
+    }
+
+    async fn put(&self, key: String, value: Bytes, ttl: Option<Duration>) -> Result<(), StoreError> {
+        if ttl.is_some() {
+            tracing::warn!("PostgresStore does not support TTLs yet; storing without expiry");
+        }
+
+        let conn = self.pool.get().await.map_err(|err| StoreError(err.to_string()))?;
+        conn.execute(
+            "INSERT INTO kv_store (key, value) VALUES ($1, $2)
+             ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value",
+            &[&key, &value.as_ref()],
+        )
+        .await
+        .map_err(|err| StoreError(err.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError> {
+        let conn = self.pool.get().await.map_err(|err| StoreError(err.to_string()))?;
+        conn.execute("DELETE FROM kv_store WHERE key = $1", &[&key])
+            .await
+            .map_err(|err| StoreError(err.to_string()))?;
+
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), StoreError> {
+        let conn = self.pool.get().await.map_err(|err| StoreError(err.to_string()))?;
+        conn.execute("TRUNCATE TABLE kv_store", &[])
+            .await
+            .map_err(|err| StoreError(err.to_string()))?;
+
+        Ok(())
+    }
+}
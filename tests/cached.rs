@@ -0,0 +1,67 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use axum::{
+    async_trait,
+    body::Body,
+    extract::FromRequestParts,
+    http::{request::Parts, Request, StatusCode},
+    routing::get,
+    Extension, Router,
+};
+
+use tower::Service;
+use webservice_rust_workshop::Cached; // for `call`
+
+/// An extractor that records how many times it actually ran, via a shared
+/// counter stashed in the request extensions.
+#[derive(Clone)]
+struct Counted(usize);
+
+#[derive(Clone)]
+struct Counter(Arc<AtomicUsize>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Counted
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, &'static str);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let counter = parts
+            .extensions
+            .get::<Counter>()
+            .expect("Counter extension missing")
+            .0
+            .clone();
+
+        Ok(Counted(counter.fetch_add(1, Ordering::SeqCst)))
+    }
+}
+
+async fn handler(Cached(first): Cached<Counted>, Cached(second): Cached<Counted>) -> String {
+    format!("{} {}", first.0, second.0)
+}
+
+#[tokio::test]
+async fn cached_extractor_runs_inner_extraction_once_per_request() {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let mut app = Router::new()
+        .route("/", get(handler))
+        .layer(Extension(Counter(Arc::clone(&counter))));
+
+    let response = app
+        .call(Request::builder().uri("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    // Both extractions see the same memoized value (0), proving `Counted`'s
+    // extraction ran exactly once even though the handler asks for it twice.
+    assert_eq!(&body[..], b"0 0");
+}
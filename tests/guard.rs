@@ -0,0 +1,123 @@
+use std::convert::Infallible;
+
+use axum::response::{IntoResponse, Response};
+use hyper::{Body, Request, StatusCode};
+use tower::{service_fn, Service, ServiceExt};
+use webservice_rust_workshop::guard::{self, Guard, GuardedRouterBuilder, RequestParts};
+
+fn request(method: &str, uri: &str) -> Request<Body> {
+    Request::builder().method(method).uri(uri).body(Body::empty()).unwrap()
+}
+
+#[test]
+fn all_matches_only_when_every_guard_matches() {
+    let req = request("GET", "/kv/test?flag");
+    let req = {
+        let (mut parts, body) = req.into_parts();
+        parts.headers.insert("x-present", "yes".parse().unwrap());
+        Request::from_parts(parts, body)
+    };
+    let parts = RequestParts::from_request(&req);
+
+    let both_match = guard::All(vec![
+        Box::new(guard::Header::present("x-present".parse().unwrap())),
+        Box::new(guard::QueryParam::present("flag")),
+    ]);
+    assert!(both_match.check(&parts));
+
+    let one_fails = guard::All(vec![
+        Box::new(guard::Header::present("x-present".parse().unwrap())),
+        Box::new(guard::QueryParam::present("missing")),
+    ]);
+    assert!(!one_fails.check(&parts));
+}
+
+#[test]
+fn any_matches_when_at_least_one_guard_matches() {
+    let req = request("GET", "/kv/test?flag");
+    let parts = RequestParts::from_request(&req);
+
+    let one_matches = guard::Any(vec![
+        Box::new(guard::QueryParam::present("missing")),
+        Box::new(guard::QueryParam::present("flag")),
+    ]);
+    assert!(one_matches.check(&parts));
+
+    let none_match = guard::Any(vec![
+        Box::new(guard::QueryParam::present("missing")),
+        Box::new(guard::QueryParam::present("also-missing")),
+    ]);
+    assert!(!none_match.check(&parts));
+}
+
+#[test]
+fn not_inverts_the_inner_guard() {
+    let req = request("GET", "/kv/test");
+    let parts = RequestParts::from_request(&req);
+
+    assert!(guard::Not(Box::new(guard::QueryParam::present("flag"))).check(&parts));
+
+    let req = request("GET", "/kv/test?flag");
+    let parts = RequestParts::from_request(&req);
+    assert!(!guard::Not(Box::new(guard::QueryParam::present("flag"))).check(&parts));
+}
+
+#[test]
+fn header_exact_requires_the_value_to_match_too() {
+    let req = {
+        let (mut parts, body) = request("GET", "/kv/test").into_parts();
+        parts.headers.insert("x-flavor", "chocolate".parse().unwrap());
+        Request::from_parts(parts, body)
+    };
+    let parts = RequestParts::from_request(&req);
+
+    assert!(guard::Header::exact("x-flavor".parse().unwrap(), "chocolate".parse().unwrap()).check(&parts));
+    assert!(!guard::Header::exact("x-flavor".parse().unwrap(), "vanilla".parse().unwrap()).check(&parts));
+    assert!(guard::Header::present("x-flavor".parse().unwrap()).check(&parts));
+    assert!(!guard::Header::present("x-missing".parse().unwrap()).check(&parts));
+}
+
+#[test]
+fn method_guard_matches_the_request_method() {
+    let req = request("POST", "/kv/test");
+    let parts = RequestParts::from_request(&req);
+
+    assert!(guard::Method(hyper::Method::POST).check(&parts));
+    assert!(!guard::Method(hyper::Method::GET).check(&parts));
+}
+
+fn respond(body: &'static str) -> impl Service<Request<Body>, Response = Response, Error = Infallible, Future = impl Send> + Clone {
+    service_fn(move |_req: Request<Body>| async move { Ok::<_, Infallible>(body.into_response()) })
+}
+
+#[tokio::test]
+async fn guarded_router_dispatches_by_content_type_and_falls_through_to_always() {
+    let mut router = GuardedRouterBuilder::new()
+        .route(guard::ContentType("application/json"), respond("json"))
+        .route(guard::Always, respond("raw"))
+        .build();
+
+    let json_req = {
+        let (mut parts, body) = request("POST", "/kv/test").into_parts();
+        parts.headers.insert(axum::http::header::CONTENT_TYPE, "application/json".parse().unwrap());
+        Request::from_parts(parts, body)
+    };
+    let response = router.ready().await.unwrap().call(json_req).await.unwrap();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(&body[..], b"json");
+
+    let raw_req = request("POST", "/kv/test");
+    let response = router.ready().await.unwrap().call(raw_req).await.unwrap();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    assert_eq!(&body[..], b"raw");
+}
+
+#[tokio::test]
+async fn guarded_router_returns_404_when_no_guard_matches() {
+    let mut router = GuardedRouterBuilder::new()
+        .route(guard::ContentType("application/json"), respond("json"))
+        .build();
+
+    let response = router.ready().await.unwrap().call(request("POST", "/kv/test")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,52 @@
+use axum::{body::Body, http::Request};
+use hyper::StatusCode;
+use tower::Service;
+use webservice_rust_workshop::{router, MemoryStore, SharedState};
+
+/// `/kv/:key` POST is dispatched by `Content-Type` (see `src/guard.rs`): a
+/// JSON body goes through `handler_kv_post_json`, which validates the body
+/// and rejects malformed JSON, while anything else falls through to
+/// `handler_kv_post`, which accepts arbitrary bytes.
+#[tokio::test]
+async fn json_content_type_is_validated_while_raw_bytes_are_not() {
+    let state = SharedState::<MemoryStore>::default();
+    let mut app = router(&state);
+
+    let response = app
+        .call(
+            Request::builder()
+                .uri("/kv/json-key")
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{"hello":"world"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .call(
+            Request::builder()
+                .uri("/kv/bad-json-key")
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .body(Body::from("not json"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .call(
+            Request::builder()
+                .uri("/kv/raw-key")
+                .method("POST")
+                .body(Body::from("not json"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
@@ -4,12 +4,12 @@ use axum::{
 };
 
 use tower::Service;
-use webservice_rust_workshop::{router, SharedState}; // for `call`
+use webservice_rust_workshop::{router, MemoryStore, SharedState}; // for `call`
 
 #[ignore]
 #[tokio::test]
 async fn no_auth() {
-    let state = SharedState::default();
+    let state = SharedState::<MemoryStore>::default();
     let mut app = router(&state);
 
     // Add something
@@ -58,7 +58,7 @@ async fn no_auth() {
 #[ignore]
 #[tokio::test]
 async fn delete_entries() {
-    let state = SharedState::default();
+    let state = SharedState::<MemoryStore>::default();
     let mut app = router(&state);
 
     // Add something
@@ -121,7 +121,7 @@ async fn delete_entries() {
 #[ignore]
 #[tokio::test]
 async fn delete_keys() {
-    let state = SharedState::default();
+    let state = SharedState::<MemoryStore>::default();
     let mut app = router(&state);
 
     // Add something
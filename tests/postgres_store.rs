@@ -0,0 +1,21 @@
+use webservice_rust_workshop::{PostgresStore, Store};
+
+// Requires a reachable Postgres with a
+// `kv_store(key TEXT PRIMARY KEY, value BYTEA)` table; point
+// `DATABASE_URL` at it to run this locally or in CI.
+#[ignore]
+#[tokio::test]
+async fn connects_and_round_trips() {
+    let config = std::env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "host=localhost user=postgres password=postgres dbname=postgres".to_owned());
+
+    let store = PostgresStore::connect(&config)
+        .await
+        .expect("failed to connect to postgres");
+
+    store.put("test-key".to_owned(), "test-value".into(), None).await.unwrap();
+    assert_eq!(store.get("test-key").await.unwrap().unwrap(), "test-value");
+
+    store.delete("test-key").await.unwrap();
+    assert!(store.get("test-key").await.unwrap().is_none());
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use webservice_rust_workshop::{MemoryStore, Store};
+
+#[tokio::test]
+async fn entry_without_ttl_never_expires() {
+    let store = MemoryStore::default();
+    store.put("key".to_owned(), "value".into(), None).await.unwrap();
+
+    assert_eq!(store.get("key").await.unwrap().unwrap(), "value");
+}
+
+#[tokio::test]
+async fn expired_entry_is_not_returned() {
+    let store = MemoryStore::default();
+    store
+        .put("key".to_owned(), "value".into(), Some(Duration::from_millis(10)))
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    assert!(store.get("key").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn sweep_expired_removes_only_expired_keys() {
+    let store = MemoryStore::default();
+    store
+        .put("expires".to_owned(), "soon".into(), Some(Duration::from_millis(10)))
+        .await
+        .unwrap();
+    store.put("stays".to_owned(), "forever".into(), None).await.unwrap();
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    store.sweep_expired().await;
+
+    assert!(store.get("expires").await.unwrap().is_none());
+    assert_eq!(store.get("stays").await.unwrap().unwrap(), "forever");
+}